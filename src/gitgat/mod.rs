@@ -1,17 +1,35 @@
 extern crate git2;
+extern crate globset;
+extern crate rayon;
+extern crate serde;
+extern crate serde_json;
 
+use std::cell::RefCell;
 use std::cmp;
+use std::collections::HashMap;
 use std::error;
 use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
 use std::time;
 
-use indicatif::{ProgressBar, ProgressIterator, ProgressStyle};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
+use serde::Serialize;
 
 type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug)]
 pub enum Error {
     Git(git2::Error),
+    Glob(globset::Error),
+    Io(io::Error),
+    Backend(String),
 }
 
 impl fmt::Display for Error {
@@ -20,6 +38,9 @@ impl fmt::Display for Error {
             // The wrapped error contains additional information and is available
             // via the source() method.
             Error::Git(err) => write!(f, "encountered a git error: {}", err),
+            Error::Glob(err) => write!(f, "encountered an invalid exclude pattern: {}", err),
+            Error::Io(err) => write!(f, "encountered an I/O error: {}", err),
+            Error::Backend(msg) => write!(f, "git backend error: {}", msg),
         }
     }
 }
@@ -28,6 +49,9 @@ impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match *self {
             Error::Git(ref e) => Some(e),
+            Error::Glob(ref e) => Some(e),
+            Error::Io(ref e) => Some(e),
+            Error::Backend(_) => None,
         }
     }
 }
@@ -38,28 +62,175 @@ impl From<git2::Error> for Error {
     }
 }
 
+impl From<globset::Error> for Error {
+    fn from(err: globset::Error) -> Error {
+        Error::Glob(err)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+/// Selects which subsystem walks history and counts line changes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// Diff commits with libgit2, in parallel. Works without `git` installed.
+    Git2,
+    /// Shell out to `git log --numstat`. Faster on large histories.
+    Git,
+}
+
+/// Selects how results are printed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A short human-readable summary with a terminal sparkline.
+    Text,
+    /// A single `serde_json`-serialised payload for scripting and dashboards.
+    Json,
+}
+
 /// Options for running gitgat.
 pub struct Opts<'a> {
     pub repo: &'a str,
-    pub author: &'a str,
+    /// Names and/or emails identifying the contributor. A commit matches if
+    /// its author name or email is one of these, or resolves to the same
+    /// canonical identity as one of these via `mailmap`.
+    pub authors: Vec<&'a str>,
+    /// Glob patterns (e.g. `src/**/*.rs`, `*.lock`) matched against each
+    /// changed file's path; matching files are excluded from the diff walk.
     pub excluded_dirs: Vec<&'a str>,
+    /// Number of hottest files to report in the churn ranking.
+    pub top: usize,
+    /// Whether to additionally report how much of the author's historical
+    /// contributions still survive in HEAD, via `git blame`.
+    pub ownership: bool,
+    /// Which subsystem to use for walking history and counting line changes.
+    pub backend: Backend,
+    /// Which format to print the result in.
+    pub format: Format,
+    /// Path to a `.mailmap`-style file aliasing historical names/emails to a
+    /// single canonical identity.
+    pub mailmap: Option<&'a str>,
 }
 
+/// Compiles the supplied glob patterns into a single `GlobSet`.
+fn build_exclusion_set(patterns: &[&str]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+    }
+    Ok(builder.build()?)
+}
+
+/// Parses a simplified `.mailmap`-style alias file into a table mapping
+/// every name/email seen on a line — aliases *and* the canonical identity
+/// itself — to one shared key, the canonical email. Supports the common
+/// subset of the mailmap format:
+///   Canonical Name <canonical@email>
+///   Canonical Name <canonical@email> <alias@email>
+///   Canonical Name <canonical@email> Alias Name <alias@email>
+/// Blank lines and lines starting with `#` are ignored.
+fn parse_mailmap(contents: &str) -> HashMap<String, String> {
+    let mut aliases = HashMap::new();
+
+    for raw_line in contents.lines() {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut canonical_name: Option<String> = None;
+        let mut canonical_email: Option<String> = None;
+        let mut pending_alias_name: Option<String> = None;
+        let mut rest = line;
+
+        while let Some(open) = rest.find('<') {
+            let before = rest[..open].trim();
+            let after_open = &rest[open + 1..];
+            let close = match after_open.find('>') {
+                Some(c) => c,
+                None => break,
+            };
+            let email = after_open[..close].trim().to_owned();
+            rest = &after_open[close + 1..];
+
+            match &canonical_email {
+                None => {
+                    if !before.is_empty() {
+                        canonical_name = Some(before.to_owned());
+                    }
+                    aliases.insert(email.clone(), email.clone());
+                    if let Some(name) = &canonical_name {
+                        aliases.insert(name.clone(), email.clone());
+                    }
+                    canonical_email = Some(email);
+                }
+                Some(canonical) => {
+                    if !before.is_empty() {
+                        pending_alias_name = Some(before.to_owned());
+                    }
+                    if let Some(alias_name) = pending_alias_name.take() {
+                        aliases.insert(alias_name, canonical.clone());
+                    }
+                    aliases.insert(email, canonical.clone());
+                }
+            }
+        }
+    }
+    aliases
+}
+
+/// Resolves a single author name or email through the mailmap alias table,
+/// falling back to the token itself when there is no alias entry.
+fn canonicalize<'a>(token: &'a str, mailmap: &'a HashMap<String, String>) -> &'a str {
+    mailmap.get(token).map(String::as_str).unwrap_or(token)
+}
+
+/// Returns true if a commit's author name/email matches one of the target
+/// identities, either directly or after resolving both through `mailmap`.
+fn matches_author(
+    name: Option<&str>,
+    email: Option<&str>,
+    targets: &[&str],
+    mailmap: &HashMap<String, String>,
+) -> bool {
+    let canonical_name = name.map(|n| canonicalize(n, mailmap));
+    let canonical_email = email.map(|e| canonicalize(e, mailmap));
+    targets.iter().any(|&target| {
+        if Some(target) == name || Some(target) == email {
+            return true;
+        }
+        let canonical_target = canonicalize(target, mailmap);
+        canonical_name == Some(canonical_target) || canonical_email == Some(canonical_target)
+    })
+}
+
+/// Per-file line additions/deletions accrued by a single commit.
+type FileDelta = HashMap<PathBuf, (u32, u32)>;
+
 #[derive(Default)]
 struct Commit {
     hash: String,
     summary: String,
     additions: u32,
     deletions: u32,
+    files: FileDelta,
+    /// Committer time, as seconds since the Unix epoch.
+    time: i64,
 }
 
 impl Commit {
-    fn new(hash: String, summary: String) -> Commit {
+    fn new(hash: String, summary: String, time: i64) -> Commit {
         Commit {
             hash: hash,
             summary: summary,
             additions: 0,
             deletions: 0,
+            files: FileDelta::default(),
+            time: time,
         }
     }
 
@@ -67,6 +238,7 @@ impl Commit {
         Commit::new(
             c.id().to_string().to_owned(),
             c.summary().unwrap_or("<unknown summary>").to_owned(),
+            c.time().seconds(),
         )
     }
     fn size(&self) -> u32 {
@@ -99,29 +271,272 @@ impl<'a> Stats<'a> {
     }
 }
 
+/// Aggregate churn for a single file across all of the author's commits.
+#[derive(Default, Clone)]
+struct FileChurn {
+    commits: u32,
+    additions: u32,
+    deletions: u32,
+}
+
+impl FileChurn {
+    fn lines_changed(&self) -> u32 {
+        self.additions + self.deletions
+    }
+}
+
 #[derive(Default)]
 struct History {
     commits: Vec<Commit>,
+    churn: HashMap<PathBuf, FileChurn>,
 }
 
 impl<'a> History {
     fn stats(&'a self) -> Stats<'a> {
         self.commits.iter().fold(Stats::default(), Stats::update)
     }
+
+    /// Folds a commit's per-file deltas into the running churn totals.
+    fn record_churn(&mut self, files: &FileDelta) {
+        for (path, &(additions, deletions)) in files {
+            let entry = self.churn.entry(path.clone()).or_default();
+            entry.commits += 1;
+            entry.additions += additions;
+            entry.deletions += deletions;
+        }
+    }
+
+    /// Returns the `top` most frequently changed files, ranked by number of
+    /// commits touching them, with total lines changed as a tiebreaker.
+    fn hotspots(&self, top: usize) -> Vec<(&PathBuf, &FileChurn)> {
+        let mut files: Vec<(&PathBuf, &FileChurn)> = self.churn.iter().collect();
+        files.sort_by(|(path_a, a), (path_b, b)| {
+            b.commits
+                .cmp(&a.commits)
+                .then_with(|| b.lines_changed().cmp(&a.lines_changed()))
+                .then_with(|| path_a.cmp(path_b))
+        });
+        files.truncate(top);
+        files
+    }
+
+    /// Buckets commits by calendar day (UTC) into a chronological series of
+    /// (date, commit count) pairs.
+    fn activity(&self) -> Vec<(String, u32)> {
+        let mut buckets: HashMap<i64, u32> = HashMap::new();
+        for commit in &self.commits {
+            *buckets.entry(commit.time.div_euclid(SECONDS_PER_DAY)).or_insert(0) += 1;
+        }
+        let mut days: Vec<(i64, u32)> = buckets.into_iter().collect();
+        days.sort_by_key(|(day, _)| *day);
+        days.into_iter().map(|(day, count)| (format_date(day), count)).collect()
+    }
 }
 
-/// Run gitgat on a repository.
-pub fn run(opts: Opts) -> Result<()> {
-    let repo = git2::Repository::open(opts.repo)?;
-    let oids = collect_oids(&repo)?;
+const SECONDS_PER_DAY: i64 = 86_400;
 
-    let mut history = History::default();
-    for i in (0..oids.len()).progress_with_style(oid_progress_style()) {
-        let commit = repo.find_commit(oids[i])?;
-        if commit.author().name() != Some(opts.author) {
-            continue;
+/// Converts a day count since the Unix epoch into an ISO-8601 `YYYY-MM-DD`
+/// date, using Howard Hinnant's `civil_from_days` algorithm so no calendar
+/// dependency is needed for a single formatting helper.
+fn format_date(days_since_epoch: i64) -> String {
+    let z = days_since_epoch + 719_468;
+    let era = (if z >= 0 { z } else { z - 146_096 }) / 146_097;
+    let doe = (z - era * 146_097) as u64;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let y = yoe as i64 + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = if month <= 2 { y + 1 } else { y };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Renders a compact terminal sparkline using eighth-block characters,
+/// scaled relative to the series' maximum value.
+fn sparkline(counts: &[u32]) -> String {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = counts.iter().cloned().max().unwrap_or(0).max(1) as f64;
+    counts
+        .iter()
+        .map(|&count| {
+            let scaled = (count as f64 / max) * (BLOCKS.len() - 1) as f64;
+            BLOCKS[scaled.round() as usize]
+        })
+        .collect()
+}
+
+/// Serialisable summary of a run, produced when `Format::Json` is selected.
+#[derive(Serialize)]
+struct JsonReport {
+    commits: u32,
+    additions: u32,
+    deletions: u32,
+    biggest_commit: Option<JsonCommit>,
+    hotspots: Vec<JsonFileChurn>,
+    activity: Vec<JsonActivityBucket>,
+    ownership: Option<JsonOwnership>,
+}
+
+#[derive(Serialize)]
+struct JsonCommit {
+    hash: String,
+    summary: String,
+    size: u32,
+}
+
+#[derive(Serialize)]
+struct JsonFileChurn {
+    path: String,
+    commits: u32,
+    additions: u32,
+    deletions: u32,
+}
+
+#[derive(Serialize)]
+struct JsonActivityBucket {
+    date: String,
+    commits: u32,
+}
+
+#[derive(Serialize)]
+struct JsonOwnership {
+    surviving: u32,
+    total: u32,
+    percentage: f64,
+}
+
+/// How much of the author's historically-contributed code survives in HEAD.
+struct Ownership {
+    surviving: u32,
+    total: u32,
+}
+
+impl Ownership {
+    fn percentage(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            100.0 * self.surviving as f64 / self.total as f64
+        }
+    }
+}
+
+/// Blames a single blob, returning the number of lines still attributed to
+/// one of `targets` and the blob's total line count. Returns `None` for
+/// blobs that are binary or not valid UTF-8, since blame is meaningless for
+/// those.
+fn blame_blob(
+    repo: &git2::Repository,
+    path: &Path,
+    targets: &[&str],
+    mailmap: &HashMap<String, String>,
+) -> Result<Option<(u32, u32)>> {
+    let blob = match repo.head()?.peel_to_tree()?.get_path(path) {
+        Ok(entry) => repo.find_blob(entry.id())?,
+        Err(_) => return Ok(None),
+    };
+    if blob.is_binary() {
+        return Ok(None);
+    }
+    let content = match std::str::from_utf8(blob.content()) {
+        Ok(content) => content,
+        Err(_) => return Ok(None),
+    };
+    let total = content.lines().count() as u32;
+
+    let blame = repo.blame_file(path, Some(&mut git2::BlameOptions::new()))?;
+    let surviving = blame
+        .iter()
+        .filter(|hunk| {
+            let sig = hunk.final_signature();
+            matches_author(sig.name(), sig.email(), targets, mailmap)
+        })
+        .map(|hunk| hunk.lines_in_hunk() as u32)
+        .sum();
+    Ok(Some((surviving, total)))
+}
+
+/// Walks the HEAD tree and sums, over every non-excluded blob, how many of
+/// its lines are still attributed to one of `targets` via `git blame`.
+fn compute_ownership(
+    repo: &git2::Repository,
+    targets: &[&str],
+    mailmap: &HashMap<String, String>,
+    excluded: &GlobSet,
+) -> Result<Ownership> {
+    let head = repo.head()?.peel_to_tree()?;
+    let mut ownership = Ownership { surviving: 0, total: 0 };
+    let mut error = None;
+
+    head.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+        if entry.kind() != Some(git2::ObjectType::Blob) {
+            return 0;
+        }
+        let path = Path::new(dir).join(entry.name().unwrap_or_default());
+        if excluded.is_match(&path) {
+            return 0;
+        }
+        match blame_blob(repo, &path, targets, mailmap) {
+            Ok(Some((surviving, total))) => {
+                ownership.surviving += surviving;
+                ownership.total += total;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                error = Some(e);
+                return -1;
+            }
+        }
+        0
+    })?;
+
+    match error {
+        Some(e) => Err(e),
+        None => Ok(ownership),
+    }
+}
+
+thread_local! {
+    /// Each rayon worker lazily opens and caches its own repository handle,
+    /// since `git2::Repository` is not `Send` and cannot be shared across threads.
+    static THREAD_REPO: RefCell<Option<git2::Repository>> = const { RefCell::new(None) };
+}
+
+/// Diffs a single commit against its parent, using (and lazily populating)
+/// the calling thread's cached repository handle. Returns `None` if the
+/// commit was not authored by one of `targets`, or if it is a root commit
+/// (no parent to diff against) or a merge commit (more than one parent) —
+/// mirroring the `git --no-merges` numstat backend's exclusions, so both
+/// backends walk the same set of commits.
+fn process_commit(
+    repo_path: &str,
+    oid: git2::Oid,
+    targets: &[&str],
+    mailmap: &HashMap<String, String>,
+    excluded: &GlobSet,
+) -> Result<Option<Commit>> {
+    THREAD_REPO.with(|cell| -> Result<Option<Commit>> {
+        let mut slot = cell.borrow_mut();
+        if slot.is_none() {
+            *slot = Some(git2::Repository::open(repo_path)?);
+        }
+        let repo = slot.as_ref().unwrap();
+
+        let commit = repo.find_commit(oid)?;
+        if commit.parent_count() != 1 {
+            return Ok(None);
+        }
+        // Scoped so the `Signature`'s borrow of `commit` ends before it is moved
+        // into `Commit::new_from_commit` below.
+        let matches = {
+            let author = commit.author();
+            matches_author(author.name(), author.email(), targets, mailmap)
+        };
+        if !matches {
+            return Ok(None);
         }
-        let prev_commit = repo.find_commit(oids[i + 1])?;
+        let prev_commit = commit.parent(0)?;
         let diff = repo.diff_tree_to_tree(
             Some(&prev_commit.tree()?),
             Some(&commit.tree()?),
@@ -139,32 +554,322 @@ pub fn run(opts: Opts) -> Result<()> {
             None,
             Some(
                 &mut |delta: git2::DiffDelta, _, line: git2::DiffLine| -> bool {
-                    // Skip if the line if it is in an excluded directory.
-                    if opts
-                        .excluded_dirs
-                        .iter()
-                        .any(|dir| delta.new_file().path().unwrap().starts_with(dir))
-                    {
+                    let path = delta.new_file().path().unwrap();
+                    // Skip the line if its path matches an excluded glob.
+                    if excluded.is_match(path) {
                         return true;
                     };
+                    let entry = c.files.entry(path.to_path_buf()).or_insert((0, 0));
                     match line.origin() {
-                        '+' => c.additions += 1,
-                        '-' => c.deletions += 1,
+                        '+' => {
+                            c.additions += 1;
+                            entry.0 += 1;
+                        }
+                        '-' => {
+                            c.deletions += 1;
+                            entry.1 += 1;
+                        }
                         _ => {}
                     };
                     return true;
                 },
             ),
         )?;
-        history.commits.push(c);
+        Ok(Some(c))
+    })
+}
+
+/// Marks the start of a commit record in the `git log` output below, chosen
+/// to never collide with a commit hash or summary.
+const NUMSTAT_COMMIT_MARKER: &str = "\u{1}";
+
+/// Collects and diffs the author's commits by shelling out to `git log
+/// --numstat`, which is markedly faster than libgit2 on large histories.
+///
+/// Root commits (no parent) are excluded from the walk: the git2 backend
+/// diffs each commit against its parent and so skips any commit that has
+/// no parent to diff against, and the two backends must agree on
+/// commit counts for the same repository and author.
+fn run_git_numstat_backend(
+    opts: &Opts,
+    mailmap: &HashMap<String, String>,
+    excluded: &GlobSet,
+) -> Result<History> {
+    let roots = Command::new("git")
+        .arg("-C")
+        .arg(opts.repo)
+        .arg("rev-list")
+        .arg("--max-parents=0")
+        .arg("HEAD")
+        .output()?;
+    if !roots.status.success() {
+        return Err(Error::Backend(format!(
+            "git rev-list exited with {}: {}",
+            roots.status,
+            String::from_utf8_lossy(&roots.stderr)
+        )));
+    }
+    let root_excludes: Vec<String> = String::from_utf8_lossy(&roots.stdout)
+        .lines()
+        .map(|oid| format!("^{}", oid))
+        .collect();
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(opts.repo)
+        .arg("log")
+        .arg("--numstat")
+        .arg("--no-merges")
+        .arg(format!(
+            "--pretty=format:{}%H\u{1f}%ct\u{1f}%an\u{1f}%ae\u{1f}%s",
+            NUMSTAT_COMMIT_MARKER
+        ))
+        .arg("HEAD")
+        .args(&root_excludes)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(Error::Backend(format!(
+            "git log exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+    let stdout = String::from_utf8(output.stdout)
+        .map_err(|_| Error::Backend("git log produced non-UTF-8 output".to_owned()))?;
+
+    Ok(parse_numstat(&stdout, &opts.authors, mailmap, excluded))
+}
+
+/// Resolves the post-rename path out of a `git --numstat` path field. Renames
+/// are reported either as a whole-path `old => new`, or, when old and new
+/// share a prefix/suffix, as `prefix{old => new}suffix`. Non-renamed paths
+/// are returned unchanged.
+fn resolve_numstat_path(raw: &str) -> PathBuf {
+    if let (Some(open), Some(close)) = (raw.find('{'), raw.rfind('}')) {
+        if open < close {
+            if let Some(arrow) = raw[open + 1..close].find(" => ") {
+                let prefix = &raw[..open];
+                let suffix = &raw[close + 1..];
+                let new = &raw[open + 1..close][arrow + 4..];
+                return PathBuf::from(format!("{}{}{}", prefix, new, suffix));
+            }
+        }
+    }
+    match raw.find(" => ") {
+        Some(arrow) => PathBuf::from(&raw[arrow + 4..]),
+        None => PathBuf::from(raw),
+    }
+}
+
+/// Parses `git log --numstat` output produced by `run_git_numstat_backend`
+/// into a `History`, keeping only commits authored by one of `targets` and
+/// applying the glob exclusion to each changed path.
+fn parse_numstat(
+    output: &str,
+    targets: &[&str],
+    mailmap: &HashMap<String, String>,
+    excluded: &GlobSet,
+) -> History {
+    let mut history = History::default();
+    let mut current: Option<Commit> = None;
+
+    let flush = |history: &mut History, commit: Option<Commit>| {
+        if let Some(c) = commit {
+            history.record_churn(&c.files);
+            history.commits.push(c);
+        }
+    };
+
+    for line in output.lines() {
+        if let Some(header) = line.strip_prefix(NUMSTAT_COMMIT_MARKER) {
+            flush(&mut history, current.take());
+            let mut parts = header.splitn(5, '\u{1f}');
+            let hash = parts.next().unwrap_or_default().to_owned();
+            let time: i64 = parts.next().unwrap_or_default().parse().unwrap_or(0);
+            let name = parts.next().unwrap_or_default();
+            let email = parts.next().unwrap_or_default();
+            let summary = parts.next().unwrap_or_default().to_owned();
+            current = if matches_author(Some(name), Some(email), targets, mailmap) {
+                Some(Commit::new(hash, summary, time))
+            } else {
+                None
+            };
+            continue;
+        }
+        let c = match current.as_mut() {
+            Some(c) => c,
+            None => continue,
+        };
+        let mut fields = line.splitn(3, '\t');
+        match (fields.next(), fields.next(), fields.next()) {
+            // Binary files are reported as `-\t-\tpath` by --numstat.
+            (Some("-"), Some(_), Some(_)) | (Some(_), Some("-"), Some(_)) => continue,
+            (Some(added), Some(deleted), Some(path)) => {
+                let path = resolve_numstat_path(path);
+                if excluded.is_match(&path) {
+                    continue;
+                }
+                let added: u32 = added.parse().unwrap_or(0);
+                let deleted: u32 = deleted.parse().unwrap_or(0);
+                c.additions += added;
+                c.deletions += deleted;
+                let entry = c.files.entry(path).or_insert((0, 0));
+                entry.0 += added;
+                entry.1 += deleted;
+            }
+            _ => {}
+        }
     }
+    flush(&mut history, current.take());
+    history
+}
+
+/// Collects and diffs the author's commits using libgit2, in parallel.
+fn run_git2_backend(opts: &Opts, mailmap: &HashMap<String, String>, excluded: &GlobSet) -> Result<History> {
+    let repo = git2::Repository::open(opts.repo)?;
+    let oids = collect_oids(&repo)?;
+
+    let progress = Arc::new(AtomicUsize::new(0));
+    let finished = Arc::new(AtomicBool::new(false));
+    let pb = ProgressBar::new(oids.len() as u64).with_style(oid_progress_style());
+
+    let monitor_pb = pb.clone();
+    let monitor_progress = Arc::clone(&progress);
+    let monitor_finished = Arc::clone(&finished);
+    let monitor = thread::spawn(move || {
+        while !monitor_finished.load(Ordering::Relaxed) {
+            monitor_pb.set_position(monitor_progress.load(Ordering::Relaxed) as u64);
+            thread::sleep(time::Duration::from_millis(100));
+        }
+        monitor_pb.set_position(monitor_progress.load(Ordering::Relaxed) as u64);
+    });
+
+    let results: Vec<Result<Option<Commit>>> = oids
+        .into_par_iter()
+        .map(|oid| {
+            let result = process_commit(opts.repo, oid, &opts.authors, mailmap, excluded);
+            progress.fetch_add(1, Ordering::Relaxed);
+            result
+        })
+        .collect();
+
+    finished.store(true, Ordering::Relaxed);
+    monitor.join().expect("progress monitor thread panicked");
+    pb.finish();
+
+    let mut history = History::default();
+    for result in results {
+        if let Some(c) = result? {
+            history.record_churn(&c.files);
+            history.commits.push(c);
+        }
+    }
+    Ok(history)
+}
+
+/// Run gitgat on a repository.
+pub fn run(opts: Opts) -> Result<()> {
+    let excluded = build_exclusion_set(&opts.excluded_dirs)?;
+    let mailmap = match opts.mailmap {
+        Some(path) => parse_mailmap(&std::fs::read_to_string(path)?),
+        None => HashMap::new(),
+    };
+
+    let history = match opts.backend {
+        Backend::Git2 => run_git2_backend(&opts, &mailmap, &excluded)?,
+        Backend::Git => run_git_numstat_backend(&opts, &mailmap, &excluded)?,
+    };
+
     let stats = history.stats();
-    println!(" {} commits", stats.commits);
-    println!("+{}", stats.additions);
-    println!("-{}", stats.deletions);
-    println!("Biggest commit {}", &stats.top.unwrap().hash);
-    println!("Biggest commit {}", &stats.top.unwrap().size());
-    println!("Biggest commit {}", &stats.top.unwrap().summary);
+    let hotspots = history.hotspots(opts.top);
+    let activity = history.activity();
+    let ownership = if opts.ownership {
+        let repo = git2::Repository::open(opts.repo)?;
+        Some(compute_ownership(&repo, &opts.authors, &mailmap, &excluded)?)
+    } else {
+        None
+    };
+
+    match opts.format {
+        Format::Json => {
+            let report = JsonReport {
+                commits: stats.commits,
+                additions: stats.additions,
+                deletions: stats.deletions,
+                biggest_commit: stats.top.map(|c| JsonCommit {
+                    hash: c.hash.clone(),
+                    summary: c.summary.clone(),
+                    size: c.size(),
+                }),
+                hotspots: hotspots
+                    .iter()
+                    .map(|(path, churn)| JsonFileChurn {
+                        path: path.display().to_string(),
+                        commits: churn.commits,
+                        additions: churn.additions,
+                        deletions: churn.deletions,
+                    })
+                    .collect(),
+                activity: activity
+                    .iter()
+                    .map(|(date, count)| JsonActivityBucket { date: date.clone(), commits: *count })
+                    .collect(),
+                ownership: ownership.as_ref().map(|o| JsonOwnership {
+                    surviving: o.surviving,
+                    total: o.total,
+                    percentage: o.percentage(),
+                }),
+            };
+            println!("{}", serde_json::to_string_pretty(&report).expect("report is always valid JSON"));
+        }
+        Format::Text => {
+            println!(" {} commits", stats.commits);
+            println!("+{}", stats.additions);
+            println!("-{}", stats.deletions);
+            let top = match stats.top {
+                Some(top) => top,
+                None => {
+                    println!("\nNo commits found for {}", opts.authors.join(", "));
+                    return Ok(());
+                }
+            };
+            println!("Biggest commit {}", top.hash);
+            println!("Biggest commit {}", top.size());
+            println!("Biggest commit {}", top.summary);
+
+            println!("\nTop {} hottest files:", opts.top);
+            for (rank, (path, churn)) in hotspots.iter().enumerate() {
+                println!(
+                    "{:>2}. {} ({} commits, +{} -{})",
+                    rank + 1,
+                    path.display(),
+                    churn.commits,
+                    churn.additions,
+                    churn.deletions
+                );
+            }
+
+            if !activity.is_empty() {
+                let counts: Vec<u32> = activity.iter().map(|(_, count)| *count).collect();
+                println!(
+                    "\nActivity {}..{} {}",
+                    activity.first().unwrap().0,
+                    activity.last().unwrap().0,
+                    sparkline(&counts)
+                );
+            }
+
+            if let Some(ownership) = &ownership {
+                println!(
+                    "\n{} surviving lines out of {} ({:.1}% of the current codebase)",
+                    ownership.surviving,
+                    ownership.total,
+                    ownership.percentage()
+                );
+            }
+        }
+    }
     Ok(())
 }
 
@@ -193,3 +898,18 @@ fn collect_oids(repo: &git2::Repository) -> Result<Vec<git2::Oid>> {
     collector_pb.is_finished();
     return Ok(oids);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_author_resolves_aliased_name_and_canonical_email_to_the_same_identity() {
+        let mailmap = parse_mailmap("Jane Doe <jane@example.com> Jane Q <old@home.com>");
+
+        // A commit authored under the alias should match a target given as
+        // either the canonical name or the canonical email.
+        assert!(matches_author(Some("Jane Q"), Some("old@home.com"), &["Jane Doe"], &mailmap));
+        assert!(matches_author(Some("Jane Q"), Some("old@home.com"), &["jane@example.com"], &mailmap));
+    }
+}
@@ -16,7 +16,15 @@ fn build_cli() -> Command<'static> {
         .setting(AppSettings::ArgRequiredElseHelp)
         .setting(AppSettings::GlobalVersion)
         .arg(Arg::new("repo").value_name("REPO").required(true).help("Repository path"))
-        .arg(Arg::new("author").value_name("AUTHOR").required(true).help("Author name"))
+        .arg(
+            Arg::new("author")
+                .value_name("AUTHOR")
+                .required(true)
+                .multiple_values(true)
+                .use_value_delimiter(true)
+                .require_delimiter(true)
+                .help("Author name(s) and/or email(s) to match.\nMultiple identities are delimited by commas."),
+        )
         .arg(
             Arg::new("exclude")
                 .short('e')
@@ -26,7 +34,47 @@ fn build_cli() -> Command<'static> {
                 .multiple_values(true)
                 .use_value_delimiter(true)
                 .require_delimiter(true)
-                .help("Exclude changes to specified directories.\nMultiple directories are delimited by commas."),
+                .help("Exclude changes to files matching a glob pattern, e.g. `src/**/*.rs` or `*.lock`.\nMultiple patterns are delimited by commas."),
+        )
+        .arg(
+            Arg::new("top")
+                .long("top")
+                .value_name("N")
+                .takes_value(true)
+                .default_value("10")
+                .help("Number of hottest files to report."),
+        )
+        .arg(
+            Arg::new("ownership")
+                .long("ownership")
+                .alias("blame")
+                .takes_value(false)
+                .help("Report how much of the author's historical contributions survive in HEAD.\nConsiderably more expensive than the diff walk, as it blames every file."),
+        )
+        .arg(
+            Arg::new("backend")
+                .long("backend")
+                .value_name("BACKEND")
+                .takes_value(true)
+                .possible_values(["git2", "git"])
+                .default_value("git2")
+                .help("Subsystem used to walk history and count line changes.\n`git2` works without the git binary; `git` shells out to it and is faster on large histories.\nBoth skip root and merge commits; line counts can differ, as `git2` ignores blank-line changes and does not detect renames."),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .takes_value(true)
+                .possible_values(["text", "json"])
+                .default_value("text")
+                .help("Output format. `json` emits a single serialised payload for scripting."),
+        )
+        .arg(
+            Arg::new("mailmap")
+                .long("mailmap")
+                .value_name("FILE")
+                .takes_value(true)
+                .help("A .mailmap-style file aliasing historical names/emails to one canonical identity."),
         )
 }
 
@@ -34,12 +82,27 @@ fn main() {
     let matches = build_cli().get_matches();
     gitgat::run(gitgat::Opts {
         repo: matches.get_one::<String>("repo").unwrap(),
-        author: matches.get_one::<String>("author").unwrap(),
+        authors: matches.get_many::<String>("author").unwrap().map(|o| o.as_str()).collect(),
         excluded_dirs: matches
             .get_many::<String>("exclude")
             .unwrap_or_default()
             .map(|o| o.as_str())
             .collect(),
+        top: matches
+            .get_one::<String>("top")
+            .unwrap()
+            .parse()
+            .expect("--top must be a non-negative integer"),
+        ownership: matches.is_present("ownership"),
+        backend: match matches.get_one::<String>("backend").unwrap().as_str() {
+            "git" => gitgat::Backend::Git,
+            _ => gitgat::Backend::Git2,
+        },
+        format: match matches.get_one::<String>("format").unwrap().as_str() {
+            "json" => gitgat::Format::Json,
+            _ => gitgat::Format::Text,
+        },
+        mailmap: matches.get_one::<String>("mailmap").map(|o| o.as_str()),
     })
     .unwrap();
 }